@@ -41,19 +41,89 @@ pub struct SpeakerPattern {
     pub xml_id: String,
     pub last_used: u64,
     pub position_frequency: std::collections::HashMap<String, usize>,
-    pub common_followers: Vec<String>,
-    pub common_preceders: Vec<String>,
+    // Transition counts: speaker -> how many times that speaker followed/preceded this one
+    pub common_followers: std::collections::HashMap<String, usize>,
+    pub common_preceders: std::collections::HashMap<String, usize>,
     pub chapter_affinity: std::collections::HashMap<String, f64>,
     pub dialogue_length_avg: f64,
 }
 
+// Online logistic-regression model for calculate_confidence, persisted
+// alongside patterns so editor feedback can reshape the factor weights
+// instead of relying on fixed, author-chosen coefficients.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidenceModel {
+    pub weights: [f64; 5],
+    pub bias: f64,
+    pub lr: f64,
+}
+
+impl Default for ConfidenceModel {
+    fn default() -> Self {
+        // Uses the original hand-picked coefficients, so an untrained model
+        // orders candidates the same way as the old fixed-weight sum until
+        // feedback starts moving it; the sigmoid changes the scale of the
+        // result (a sum of 0.945 becomes about 0.72), not the order.
+        ConfidenceModel {
+            weights: [0.30, 0.25, 0.20, 0.10, 0.15],
+            bias: 0.0,
+            lr: 0.1,
+        }
+    }
+}
+
+// Keep weights/bias from drifting without bound under repeated feedback
+const CONFIDENCE_WEIGHT_CLAMP: f64 = 5.0;
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+// PatternMatch factors in calculate_confidence's original order, as a
+// feature vector for the logistic-regression model
+fn pattern_match_features(pattern_match: &PatternMatch) -> [f64; 5] {
+    [
+        if pattern_match.recent { 1.0 } else { 0.0 },
+        pattern_match.chapter_frequency,
+        if pattern_match.turn_taking { 1.0 } else { 0.0 },
+        if pattern_match.name_mention { 1.0 } else { 0.0 },
+        pattern_match.dialogue_length_score,
+    ]
+}
+
+fn confidence_from_features(model: &ConfidenceModel, features: &[f64; 5]) -> f64 {
+    let z: f64 = model
+        .weights
+        .iter()
+        .zip(features.iter())
+        .map(|(w, f)| w * f)
+        .sum::<f64>()
+        + model.bias;
+    sigmoid(z)
+}
+
+// One stochastic gradient step toward `target` for the given features,
+// clamped to keep weights/bias bounded.
+fn sgd_step(model: &mut ConfidenceModel, features: &[f64; 5], target: f64) {
+    let prediction = confidence_from_features(model, features);
+    let error = target - prediction;
+
+    for (weight, feature) in model.weights.iter_mut().zip(features.iter()) {
+        *weight = (*weight + model.lr * error * feature)
+            .clamp(-CONFIDENCE_WEIGHT_CLAMP, CONFIDENCE_WEIGHT_CLAMP);
+    }
+    model.bias = (model.bias + model.lr * error)
+        .clamp(-CONFIDENCE_WEIGHT_CLAMP, CONFIDENCE_WEIGHT_CLAMP);
+}
+
 /// Calculate confidence score for speaker detection
 /// Returns a value between 0.0 and 1.0
 #[wasm_bindgen]
 pub fn calculate_confidence(
     _text: &str,
     _speaker: &str,
-    patterns_json: &str
+    patterns_json: &str,
+    model_json: &str,
 ) -> f64 {
     // Parse the patterns JSON
     let pattern_match: PatternMatch = match serde_json::from_str(patterns_json) {
@@ -64,38 +134,11 @@ pub fn calculate_confidence(
         }
     };
 
-    let mut score = 0.0;
-
-    // Factor 1: Recency boost (30% weight)
-    // If this speaker spoke recently in the text, increase confidence
-    if pattern_match.recent {
-        score += 0.3;
-    }
-
-    // Factor 2: Chapter frequency (25% weight)
-    // If this speaker dominates the current chapter, increase confidence
-    score += pattern_match.chapter_frequency * 0.25;
-
-    // Factor 3: Turn-taking pattern (20% weight)
-    // If we detect an A-B-A turn-taking pattern suggesting this speaker
-    if pattern_match.turn_taking {
-        score += 0.2;
-    }
-
-    // Factor 4: Name mention in context (10% weight)
-    // If the speaker's name is mentioned in the narrative context
-    if pattern_match.name_mention {
-        score += 0.1;
-    }
-
-    // Factor 5: Dialogue length distribution (15% weight)
-    // If the dialogue length matches this speaker's typical pattern
-    score += pattern_match.dialogue_length_score * 0.15;
+    // Missing/invalid model JSON falls back to the original fixed weights
+    let model: ConfidenceModel = serde_json::from_str(model_json).unwrap_or_default();
+    let features = pattern_match_features(&pattern_match);
 
-    // Normalize to 0.0-1.0 range
-    let confidence = score.min(1.0).max(0.0);
-
-    confidence
+    confidence_from_features(&model, &features)
 }
 
 /// Store a learned pattern for a speaker
@@ -105,6 +148,7 @@ pub fn store_pattern(
     chapter: &str,
     position: usize,
     dialogue_length: f64,
+    previous_speaker: &str,
     patterns_json: &str
 ) -> String {
     // Parse existing patterns or create new
@@ -114,8 +158,8 @@ pub fn store_pattern(
             xml_id: speaker.to_string(),
             last_used: 0,
             position_frequency: std::collections::HashMap::new(),
-            common_followers: Vec::new(),
-            common_preceders: Vec::new(),
+            common_followers: std::collections::HashMap::new(),
+            common_preceders: std::collections::HashMap::new(),
             chapter_affinity: std::collections::HashMap::new(),
             dialogue_length_avg: 0.0,
         }
@@ -132,6 +176,16 @@ pub fn store_pattern(
     // Update chapter affinity
     *pattern.chapter_affinity.entry(chapter.to_string()).or_insert(0.0) += 1.0;
 
+    // Record this speaker's preceder transition count (previous_speaker -> speaker).
+    // The matching follower count on previous_speaker's own pattern is recorded by
+    // update_from_feedback, which has both patterns in scope at once.
+    if !previous_speaker.is_empty() {
+        *pattern
+            .common_preceders
+            .entry(previous_speaker.to_string())
+            .or_insert(0) += 1;
+    }
+
     // Update dialogue length average (exponential moving average)
     if pattern.dialogue_length_avg > 0.0 {
         pattern.dialogue_length_avg = pattern.dialogue_length_avg * 0.8 + dialogue_length * 0.2;
@@ -160,12 +214,23 @@ pub fn get_patterns(speaker: &str, all_patterns_json: &str) -> String {
     }
 }
 
+// Combined output of update_from_feedback: learned speaker patterns plus
+// the confidence model, retrained by this correction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackResult {
+    pub patterns: std::collections::HashMap<String, SpeakerPattern>,
+    pub model: ConfidenceModel,
+}
+
 /// Update patterns based on user feedback/corrections
 #[wasm_bindgen]
 pub fn update_from_feedback(
     passage: &str,
     accepted_speaker: &str,
+    previous_speaker: &str,
     rejected_speakers_json: &str,
+    pattern_matches_json: &str,
+    model_json: &str,
     current_patterns_json: &str
 ) -> String {
     // Parse rejected speakers
@@ -174,6 +239,22 @@ pub fn update_from_feedback(
         Err(_) => Vec::new()
     };
 
+    // Parse the PatternMatch features each candidate had at decision time
+    // (accepted_speaker plus every rejected speaker), so the confidence
+    // model can take one SGD step per correction.
+    let pattern_matches: std::collections::HashMap<String, PatternMatch> =
+        serde_json::from_str(pattern_matches_json).unwrap_or_default();
+    let mut model: ConfidenceModel = serde_json::from_str(model_json).unwrap_or_default();
+
+    if let Some(pattern_match) = pattern_matches.get(accepted_speaker) {
+        sgd_step(&mut model, &pattern_match_features(pattern_match), 1.0);
+    }
+    for rejected in &rejected_speakers {
+        if let Some(pattern_match) = pattern_matches.get(rejected) {
+            sgd_step(&mut model, &pattern_match_features(pattern_match), 0.0);
+        }
+    }
+
     // Parse current patterns
     let mut all_patterns: std::collections::HashMap<String, SpeakerPattern> =
         match serde_json::from_str(current_patterns_json) {
@@ -191,8 +272,8 @@ pub fn update_from_feedback(
             xml_id: accepted_speaker.to_string(),
             last_used: get_current_time(),
             position_frequency: std::collections::HashMap::new(),
-            common_followers: Vec::new(),
-            common_preceders: Vec::new(),
+            common_followers: std::collections::HashMap::new(),
+            common_preceders: std::collections::HashMap::new(),
             chapter_affinity: std::collections::HashMap::new(),
             dialogue_length_avg: dialogue_length,
         });
@@ -207,6 +288,23 @@ pub fn update_from_feedback(
         accepted_pattern.dialogue_length_avg = dialogue_length;
     }
 
+    // Record the A->B transition: B's (accepted speaker's) preceder count for A
+    if !previous_speaker.is_empty() {
+        *accepted_pattern
+            .common_preceders
+            .entry(previous_speaker.to_string())
+            .or_insert(0) += 1;
+    }
+
+    // Record A's (previous speaker's) follower count for B, if A has a pattern yet.
+    // An empty previous_speaker never matches a stored pattern, so no extra guard needed.
+    if let Some(previous_pattern) = all_patterns.get_mut(previous_speaker) {
+        *previous_pattern
+            .common_followers
+            .entry(accepted_speaker.to_string())
+            .or_insert(0) += 1;
+    }
+
     // Decrease confidence in rejected speakers (by not updating their last_used time)
     for rejected in rejected_speakers {
         if let Some(pattern) = all_patterns.get_mut(&rejected) {
@@ -215,8 +313,76 @@ pub fn update_from_feedback(
         }
     }
 
-    // Serialize updated patterns
-    serde_json::to_string(&all_patterns).unwrap_or_else(|_| "{}".to_string())
+    // Serialize updated patterns alongside the retrained confidence model
+    let result = FeedbackResult {
+        patterns: all_patterns,
+        model,
+    };
+    serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string())
+}
+
+// Leaf and combinator predicates evaluated against the current detection
+// context, used to force or bias speaker assignment via SpeakerRule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "predicate", content = "argument")]
+pub enum Predicate {
+    InChapter(String),
+    PositionBetween(usize, usize),
+    NameMentioned(String),
+    AfterSpeaker(String),
+    LengthBetween(f64, f64),
+    MatchesRegex(String),
+    AllOf(Vec<Predicate>),
+    AnyOf(Vec<Predicate>),
+    Not(Box<Predicate>),
+}
+
+// What to do with a candidate speaker when a rule's predicate matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", content = "value")]
+pub enum RuleAction {
+    Force,
+    Boost(f64),
+}
+
+// A user-supplied rule: assign or bias `xml_id` when `predicate` holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerRule {
+    pub xml_id: String,
+    pub predicate: Predicate,
+    pub action: RuleAction,
+}
+
+// Evaluate a predicate tree against the passage/chapter/position/previous-speaker context
+fn evaluate_predicate(
+    predicate: &Predicate,
+    text: &str,
+    chapter: &str,
+    position: usize,
+    previous_speaker: &str,
+) -> bool {
+    match predicate {
+        Predicate::InChapter(name) => chapter == name,
+        Predicate::PositionBetween(lo, hi) => position >= *lo && position <= *hi,
+        Predicate::NameMentioned(name) => text.to_lowercase().contains(&name.to_lowercase()),
+        Predicate::AfterSpeaker(id) => previous_speaker == id,
+        Predicate::LengthBetween(lo, hi) => {
+            let length = text.split_whitespace().count() as f64;
+            length >= *lo && length <= *hi
+        }
+        Predicate::MatchesRegex(pattern) => regex::Regex::new(pattern)
+            .map(|re| re.is_match(text))
+            .unwrap_or(false),
+        Predicate::AllOf(predicates) => predicates
+            .iter()
+            .all(|p| evaluate_predicate(p, text, chapter, position, previous_speaker)),
+        Predicate::AnyOf(predicates) => predicates
+            .iter()
+            .any(|p| evaluate_predicate(p, text, chapter, position, previous_speaker)),
+        Predicate::Not(inner) => {
+            !evaluate_predicate(inner, text, chapter, position, previous_speaker)
+        }
+    }
 }
 
 /// Detect speaker using pattern matching algorithm
@@ -225,8 +391,23 @@ pub fn detect_speaker(
     text: &str,
     chapter: &str,
     position: usize,
+    previous_speaker: &str,
+    rules_json: &str,
     all_patterns_json: &str
 ) -> String {
+    // Parse editor-supplied rules (invalid/absent JSON means no rules apply)
+    let rules: Vec<SpeakerRule> = serde_json::from_str(rules_json).unwrap_or_default();
+
+    // Force rules short-circuit the statistical model entirely, so they're
+    // checked before patterns are even parsed.
+    for rule in &rules {
+        if matches!(rule.action, RuleAction::Force)
+            && evaluate_predicate(&rule.predicate, text, chapter, position, previous_speaker)
+        {
+            return rule.xml_id.clone();
+        }
+    }
+
     // Parse all patterns
     let all_patterns: std::collections::HashMap<String, SpeakerPattern> =
         match serde_json::from_str(all_patterns_json) {
@@ -245,6 +426,20 @@ pub fn detect_speaker(
     let now = get_current_time();
     let dialogue_length = text.split_whitespace().count() as f64;
 
+    // Previous speaker's outgoing transition counts, used below for the
+    // P(candidate | previous) factor with Laplace (add-one) smoothing.
+    let speaker_count = all_patterns.len();
+    let previous_followers = if previous_speaker.is_empty() {
+        None
+    } else {
+        all_patterns
+            .get(previous_speaker)
+            .map(|pattern| &pattern.common_followers)
+    };
+    let previous_total_out: usize = previous_followers
+        .map(|followers| followers.values().sum())
+        .unwrap_or(0);
+
     for (speaker_id, pattern) in &all_patterns {
         let mut score = 0.0;
 
@@ -275,6 +470,29 @@ pub fn detect_speaker(
             score += length_score * 0.1;
         }
 
+        // Factor 5: Speaker-transition Markov model P(candidate | previous), with
+        // add-one smoothing over the previous speaker's learned follower counts
+        if !previous_speaker.is_empty() {
+            let transition_count = previous_followers
+                .and_then(|followers| followers.get(speaker_id))
+                .copied()
+                .unwrap_or(0);
+            let transition_score = (transition_count as f64 + 1.0)
+                / (previous_total_out as f64 + speaker_count as f64);
+            score += transition_score * 0.25;
+        }
+
+        // Boost rules compose with the statistical model rather than overriding it
+        for rule in &rules {
+            if let RuleAction::Boost(delta) = rule.action {
+                if &rule.xml_id == speaker_id
+                    && evaluate_predicate(&rule.predicate, text, chapter, position, previous_speaker)
+                {
+                    score += delta;
+                }
+            }
+        }
+
         if score > best_score {
             best_score = score;
             best_speaker = speaker_id.clone();
@@ -284,6 +502,434 @@ pub fn detect_speaker(
     best_speaker
 }
 
+// Candidate prime for each lowercase letter (a-z) and digit (0-9), used to
+// build anagram values for approximate name matching.
+const ANAGRAM_PRIMES: [u128; 36] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151,
+];
+
+// Maximum number of character edits considered when expanding anagram
+// neighbors during fuzzy name resolution.
+const ANAGRAM_MAX_EDITS: u32 = 2;
+
+// Known cast member for approximate name matching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerName {
+    pub xml_id: String,
+    pub display_name: String,
+}
+
+// Best fuzzy match for a mentioned name, with a graded confidence score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameMatch {
+    pub name: String,
+    pub score: f64,
+}
+
+// Prime assigned to a single alphanumeric character, or None if the
+// character isn't part of the anagram alphabet (punctuation, whitespace).
+fn char_prime(c: char) -> Option<u128> {
+    let c = c.to_ascii_lowercase();
+    if c.is_ascii_lowercase() {
+        Some(ANAGRAM_PRIMES[(c as u8 - b'a') as usize])
+    } else if c.is_ascii_digit() {
+        Some(ANAGRAM_PRIMES[26 + (c as u8 - b'0') as usize])
+    } else {
+        None
+    }
+}
+
+// Strip everything but alphanumerics and lowercase, so "Mr. Darcy" and
+// "mr darcy" collapse to the same comparable string.
+fn normalize_for_match(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+// Deterministic fallback key for tokens too long to multiply into a u128
+// anagram value without overflow. Still collision-checked by downstream
+// Damerau-Levenshtein verification, and still anagram-stable since it
+// hashes the sorted character multiset.
+fn collision_checked_hash(normalized: &str) -> u128 {
+    let mut chars: Vec<char> = normalized.chars().collect();
+    chars.sort_unstable();
+
+    let mut hash: u128 = 0xcbf29ce484222325;
+    for c in chars {
+        hash ^= c as u128;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// Anagram value for a token: the product of its characters' primes, or a
+// collision-checked hash of the same character multiset if that product
+// would overflow u128.
+fn anagram_value(token: &str) -> Option<u128> {
+    let normalized = normalize_for_match(token);
+    if normalized.is_empty() {
+        return None;
+    }
+
+    let mut value: u128 = 1;
+    for c in normalized.chars() {
+        let prime = char_prime(c)?;
+        match value.checked_mul(prime) {
+            Some(next) => value = next,
+            None => return Some(collision_checked_hash(&normalized)),
+        }
+    }
+    Some(value)
+}
+
+// Anagram values reachable from `value` within `max_edits` character
+// deletions/insertions, by dividing out or multiplying in candidate primes.
+// Substitutions fall out naturally since a delete followed by an insert
+// costs two of the edit budget.
+fn anagram_neighbors(value: u128, max_edits: u32) -> std::collections::HashSet<u128> {
+    let mut all = std::collections::HashSet::new();
+    all.insert(value);
+
+    let mut frontier = all.clone();
+    for _ in 0..max_edits {
+        let mut next = std::collections::HashSet::new();
+        for &v in &frontier {
+            for &prime in ANAGRAM_PRIMES.iter() {
+                if v % prime == 0 {
+                    next.insert(v / prime);
+                }
+                if let Some(product) = v.checked_mul(prime) {
+                    next.insert(product);
+                }
+            }
+        }
+        all.extend(next.iter().copied());
+        frontier = next;
+    }
+
+    all
+}
+
+// Damerau-Levenshtein edit distance between two character sequences, in
+// its restricted (optimal string alignment) form: an adjacent transposition
+// counts as one edit, but no substring is edited more than once.
+fn damerau_levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (la, lb) = (a.len(), b.len());
+
+    let mut d = vec![vec![0usize; lb + 1]; la + 1];
+    for (i, row) in d.iter_mut().enumerate().take(la + 1) {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate().take(lb + 1) {
+        *cell = j;
+    }
+
+    for i in 1..=la {
+        for j in 1..=lb {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + cost);
+            }
+        }
+    }
+
+    d[la][lb]
+}
+
+// Score a candidate name against a mentioned token, combining normalized
+// edit distance with a length-ratio penalty so "Bess" doesn't outscore
+// "Elizabeth" just because it happens to be two edits from both.
+fn score_name_candidate(token: &str, candidate: &str) -> f64 {
+    let token_norm = normalize_for_match(token);
+    let candidate_norm = normalize_for_match(candidate);
+
+    let distance = damerau_levenshtein(&token_norm, &candidate_norm) as f64;
+    let token_len = token_norm.chars().count().max(1) as f64;
+    let candidate_len = candidate_norm.chars().count().max(1) as f64;
+    let max_len = token_len.max(candidate_len);
+
+    let edit_score = (1.0 - distance / max_len).max(0.0);
+    let length_ratio = token_len.min(candidate_len) / max_len;
+
+    (edit_score * 0.7 + length_ratio * 0.3).clamp(0.0, 1.0)
+}
+
+// Single words plus adjacent-word bigrams, so multi-word names like
+// "Mr. Darcy" can be matched as a unit in addition to "Darcy" alone.
+fn extract_candidate_tokens(text: &str) -> Vec<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_string())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let mut tokens = words.clone();
+    for pair in words.windows(2) {
+        tokens.push(format!("{} {}", pair[0], pair[1]));
+    }
+    tokens
+}
+
+// Anagram value of each speaker's display name and of its xml_id -> the
+// display names of the speakers indexed under that value
+fn build_anagram_index(
+    speakers: &[SpeakerName],
+) -> std::collections::HashMap<u128, Vec<String>> {
+    let mut index: std::collections::HashMap<u128, Vec<String>> =
+        std::collections::HashMap::new();
+
+    for speaker in speakers {
+        for key in [&speaker.display_name, &speaker.xml_id] {
+            if let Some(value) = anagram_value(key) {
+                let names = index.entry(value).or_default();
+                if !names.contains(&speaker.display_name) {
+                    names.push(speaker.display_name.clone());
+                }
+            }
+        }
+    }
+
+    index
+}
+
+/// Resolve a graded fuzzy match for a mentioned character name
+///
+/// Builds an anagram-hash index over the cast of `xml_id`/display-name
+/// pairs, expands each word/bigram in `context_text` to nearby anagram
+/// values (cheap candidate retrieval), then verifies candidates with exact
+/// Damerau-Levenshtein (optimal string alignment) distance so OCR noise, inflected forms, and spelling
+/// variants (e.g. "Elisabeth" vs "Elizabeth") score as a continuous signal
+/// rather than a boolean flag.
+#[wasm_bindgen]
+pub fn resolve_name_mention(context_text: &str, speaker_names_json: &str) -> String {
+    let no_match = NameMatch {
+        name: String::new(),
+        score: 0.0,
+    };
+
+    let speakers: Vec<SpeakerName> = match serde_json::from_str(speaker_names_json) {
+        Ok(s) => s,
+        Err(_) => return serde_json::to_string(&no_match).unwrap_or_else(|_| "{}".to_string()),
+    };
+
+    if speakers.is_empty() {
+        return serde_json::to_string(&no_match).unwrap_or_else(|_| "{}".to_string());
+    }
+
+    let index = build_anagram_index(&speakers);
+    let mut best = no_match;
+
+    for token in extract_candidate_tokens(context_text) {
+        let Some(value) = anagram_value(&token) else {
+            continue;
+        };
+
+        for neighbor in anagram_neighbors(value, ANAGRAM_MAX_EDITS) {
+            let Some(candidates) = index.get(&neighbor) else {
+                continue;
+            };
+
+            for candidate in candidates {
+                let score = score_name_candidate(&token, candidate);
+                if score > best.score {
+                    best = NameMatch {
+                        name: candidate.clone(),
+                        score,
+                    };
+                }
+            }
+        }
+    }
+
+    serde_json::to_string(&best).unwrap_or_else(|_| "{}".to_string())
+}
+
+// Cheap function words whose relative frequency is a useful stylometric
+// fingerprint even over short dialogue passages.
+const STYLOMETRIC_FUNCTION_WORDS: [&str; 10] =
+    ["the", "and", "a", "to", "of", "in", "that", "it", "is", "was"];
+
+// A cluster proposed for a batch of unassigned passages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassageCluster {
+    pub cluster_id: usize,
+    pub passage_indices: Vec<usize>,
+    pub centroid: Vec<f64>,
+}
+
+// Working cluster during agglomerative merging (not serialized; the public
+// result is PassageCluster)
+struct PassageGroup {
+    members: Vec<usize>,
+}
+
+// Stylometric feature vector for one passage: dialogue length, average word
+// length, punctuation/question-mark ratio, and function-word frequencies.
+fn passage_features(text: &str) -> Vec<f64> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let char_count = text.chars().count().max(1) as f64;
+    let word_count = words.len().max(1) as f64;
+
+    let avg_word_length =
+        words.iter().map(|w| w.chars().count()).sum::<usize>() as f64 / word_count;
+    let punctuation_ratio =
+        text.chars().filter(|c| c.is_ascii_punctuation()).count() as f64 / char_count;
+    let question_ratio = text.chars().filter(|&c| c == '?').count() as f64 / char_count;
+
+    let mut features = vec![words.len() as f64, avg_word_length, punctuation_ratio, question_ratio];
+    for function_word in STYLOMETRIC_FUNCTION_WORDS.iter() {
+        let frequency = words.iter().filter(|w| w.as_str() == *function_word).count() as f64;
+        features.push(frequency / word_count);
+    }
+    features
+}
+
+// Scale a feature vector to unit length so passages of very different raw
+// magnitudes (long vs. short dialogue) cluster on shape, not scale.
+fn normalize_feature_vector(features: &[f64]) -> Vec<f64> {
+    let norm = features.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        features.iter().map(|v| v / norm).collect()
+    } else {
+        features.to_vec()
+    }
+}
+
+// Cosine distance (1 - cosine similarity) between two feature vectors
+fn cosine_distance(a: &[f64], b: &[f64]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|v| v * v).sum::<f64>().sqrt();
+    let norm_b = b.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        1.0
+    } else {
+        (1.0 - dot / (norm_a * norm_b)).clamp(0.0, 2.0)
+    }
+}
+
+// Average-linkage distance between two passage groups: the mean pairwise
+// cosine distance across every member of one group against every member of
+// the other.
+fn average_linkage_distance(a: &PassageGroup, b: &PassageGroup, features: &[Vec<f64>]) -> f64 {
+    let mut total = 0.0;
+    let mut count = 0usize;
+    for &i in &a.members {
+        for &j in &b.members {
+            total += cosine_distance(&features[i], &features[j]);
+            count += 1;
+        }
+    }
+    if count == 0 {
+        f64::MAX
+    } else {
+        total / count as f64
+    }
+}
+
+// Centroid (mean feature vector) for a group of passages
+fn group_centroid(members: &[usize], features: &[Vec<f64>]) -> Vec<f64> {
+    let dims = features.first().map(Vec::len).unwrap_or(0);
+    let mut sum = vec![0.0; dims];
+    for &i in members {
+        for (d, value) in features[i].iter().enumerate() {
+            sum[d] += value;
+        }
+    }
+    let count = members.len().max(1) as f64;
+    sum.into_iter().map(|v| v / count).collect()
+}
+
+/// Propose speaker-sharing clusters for a batch of unassigned passages
+///
+/// Runs bottom-up agglomerative clustering with average-linkage cosine
+/// distance over a per-passage stylometric feature vector (dialogue length,
+/// average word length, punctuation/question-mark ratio, and function-word
+/// frequencies). Starts with every passage as its own cluster and repeatedly
+/// merges the closest pair, stopping once the next merge would exceed
+/// `distance_threshold` or the cluster count reaches `target_clusters`
+/// (pass 0 to ignore the target and rely on the threshold alone). Returns
+/// each cluster's member passage indices and centroid so an editor can label
+/// a whole cluster at once, then feed it back through `update_from_feedback`.
+#[wasm_bindgen]
+pub fn cluster_passages(
+    passages_json: &str,
+    distance_threshold: f64,
+    target_clusters: usize,
+) -> String {
+    let passages: Vec<String> = match serde_json::from_str(passages_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    if passages.is_empty() {
+        return "[]".to_string();
+    }
+
+    let features: Vec<Vec<f64>> = passages
+        .iter()
+        .map(|passage| normalize_feature_vector(&passage_features(passage)))
+        .collect();
+
+    let mut groups: Vec<PassageGroup> = (0..passages.len())
+        .map(|i| PassageGroup { members: vec![i] })
+        .collect();
+
+    while groups.len() > 1 && (target_clusters == 0 || groups.len() > target_clusters) {
+        let mut best_pair = (0usize, 1usize);
+        let mut best_distance = f64::MAX;
+        for i in 0..groups.len() {
+            for j in (i + 1)..groups.len() {
+                let distance = average_linkage_distance(&groups[i], &groups[j], &features);
+                if distance < best_distance {
+                    best_distance = distance;
+                    best_pair = (i, j);
+                }
+            }
+        }
+
+        if best_distance > distance_threshold {
+            break;
+        }
+
+        let (i, j) = best_pair;
+        let mut merged = groups[i].members.clone();
+        merged.extend(groups[j].members.clone());
+        groups.remove(j);
+        groups.remove(i);
+        groups.push(PassageGroup { members: merged });
+    }
+
+    let clusters: Vec<PassageCluster> = groups
+        .into_iter()
+        .enumerate()
+        .map(|(cluster_id, group)| {
+            let mut passage_indices = group.members.clone();
+            passage_indices.sort_unstable();
+            let centroid = group_centroid(&group.members, &features);
+            PassageCluster {
+                cluster_id,
+                passage_indices,
+                centroid,
+            }
+        })
+        .collect();
+
+    serde_json::to_string(&clusters).unwrap_or_else(|_| "[]".to_string())
+}
+
 // Regular (non-wasm) tests for core logic
 #[cfg(test)]
 mod tests {
@@ -301,10 +947,11 @@ mod tests {
         };
 
         let patterns_json = serde_json::to_string(&pattern).unwrap();
-        let confidence = calculate_confidence("Test text", "speaker1", &patterns_json);
+        let confidence = calculate_confidence("Test text", "speaker1", &patterns_json, "{}");
 
-        // High confidence expected: 0.3 + 0.225 + 0.2 + 0.1 + 0.12 = 0.945
-        assert!(confidence > 0.9);
+        // With the default (untrained) weights, z = 0.3 + 0.225 + 0.2 + 0.1 + 0.12 = 0.945,
+        // so confidence = sigmoid(0.945)
+        assert!((confidence - 0.7201).abs() < 0.001);
         assert!(confidence <= 1.0);
     }
 
@@ -319,23 +966,74 @@ mod tests {
         };
 
         let patterns_json = serde_json::to_string(&pattern).unwrap();
-        let confidence = calculate_confidence("Test text", "speaker1", &patterns_json);
+        let confidence = calculate_confidence("Test text", "speaker1", &patterns_json, "{}");
 
-        // Low confidence expected: 0.0 + 0.025 + 0.0 + 0.0 + 0.03 = 0.055
-        assert!(confidence < 0.2);
+        // With the default (untrained) weights, z = 0.025 + 0.03 = 0.055,
+        // so confidence = sigmoid(0.055) — close to 0.5 since logistic
+        // regression compresses small, untrained scores toward the midpoint
+        assert!((confidence - 0.5137).abs() < 0.001);
         assert!(confidence >= 0.0);
     }
 
+    #[test]
+    fn test_calculate_confidence_high_exceeds_low() {
+        let high = PatternMatch {
+            recent: true,
+            chapter_frequency: 0.9,
+            turn_taking: true,
+            name_mention: true,
+            dialogue_length_score: 0.8,
+        };
+        let low = PatternMatch {
+            recent: false,
+            chapter_frequency: 0.1,
+            turn_taking: false,
+            name_mention: false,
+            dialogue_length_score: 0.2,
+        };
+
+        let high_confidence =
+            calculate_confidence("Test text", "speaker1", &serde_json::to_string(&high).unwrap(), "{}");
+        let low_confidence =
+            calculate_confidence("Test text", "speaker1", &serde_json::to_string(&low).unwrap(), "{}");
+
+        assert!(high_confidence > low_confidence);
+    }
+
     #[test]
     fn test_calculate_confidence_invalid_json() {
-        let confidence = calculate_confidence("Test text", "speaker1", "invalid json");
+        let confidence = calculate_confidence("Test text", "speaker1", "invalid json", "{}");
         assert_eq!(confidence, 0.5); // Default confidence on error
     }
 
+    #[test]
+    fn test_calculate_confidence_uses_trained_model() {
+        let pattern = PatternMatch {
+            recent: true,
+            chapter_frequency: 0.9,
+            turn_taking: true,
+            name_mention: true,
+            dialogue_length_score: 0.8,
+        };
+        let patterns_json = serde_json::to_string(&pattern).unwrap();
+
+        let trained_model = ConfidenceModel {
+            weights: [1.0, 1.0, 1.0, 1.0, 1.0],
+            bias: 0.0,
+            lr: 0.1,
+        };
+        let model_json = serde_json::to_string(&trained_model).unwrap();
+
+        let default_confidence = calculate_confidence("Test text", "speaker1", &patterns_json, "{}");
+        let trained_confidence = calculate_confidence("Test text", "speaker1", &patterns_json, &model_json);
+
+        assert!(trained_confidence > default_confidence);
+    }
+
     #[test]
     fn test_store_pattern_new() {
         let pattern_json = "{}";
-        let result = store_pattern("speaker1", "chapter1", 5, 25.0, pattern_json);
+        let result = store_pattern("speaker1", "chapter1", 5, 25.0, "", pattern_json);
 
         let pattern: SpeakerPattern = serde_json::from_str(&result).unwrap();
         assert_eq!(pattern.xml_id, "speaker1");
@@ -349,14 +1047,14 @@ mod tests {
             xml_id: "speaker1".to_string(),
             last_used: 1000,
             position_frequency: std::collections::HashMap::new(),
-            common_followers: Vec::new(),
-            common_preceders: Vec::new(),
+            common_followers: std::collections::HashMap::new(),
+            common_preceders: std::collections::HashMap::new(),
             chapter_affinity: std::collections::HashMap::new(),
             dialogue_length_avg: 20.0,
         };
         let pattern_json = serde_json::to_string(&existing).unwrap();
 
-        let result = store_pattern("speaker1", "chapter1", 5, 30.0, &pattern_json);
+        let result = store_pattern("speaker1", "chapter1", 5, 30.0, "", &pattern_json);
         let pattern: SpeakerPattern = serde_json::from_str(&result).unwrap();
 
         // EMA: 20.0 * 0.8 + 30.0 * 0.2 = 16.0 + 6.0 = 22.0
@@ -373,8 +1071,8 @@ mod tests {
                 xml_id: "speaker1".to_string(),
                 last_used: 12345,
                 position_frequency: std::collections::HashMap::new(),
-                common_followers: Vec::new(),
-                common_preceders: Vec::new(),
+                common_followers: std::collections::HashMap::new(),
+                common_preceders: std::collections::HashMap::new(),
                 chapter_affinity: std::collections::HashMap::new(),
                 dialogue_length_avg: 25.0,
             }
@@ -405,12 +1103,11 @@ mod tests {
         let rejected = "[]";
         let current = "{}";
 
-        let result = update_from_feedback(passage, accepted, rejected, current);
-        let all_patterns: std::collections::HashMap<String, SpeakerPattern> =
-            serde_json::from_str(&result).unwrap();
+        let result = update_from_feedback(passage, accepted, "", rejected, "{}", "{}", current);
+        let feedback: FeedbackResult = serde_json::from_str(&result).unwrap();
 
-        assert!(all_patterns.contains_key("speaker1"));
-        let pattern = &all_patterns["speaker1"];
+        assert!(feedback.patterns.contains_key("speaker1"));
+        let pattern = &feedback.patterns["speaker1"];
         assert_eq!(pattern.xml_id, "speaker1");
         // 6 words = 6.0 dialogue length
         assert_eq!(pattern.dialogue_length_avg, 6.0);
@@ -425,8 +1122,8 @@ mod tests {
                 xml_id: "speaker2".to_string(),
                 last_used: get_current_time(),
                 position_frequency: std::collections::HashMap::new(),
-                common_followers: Vec::new(),
-                common_preceders: Vec::new(),
+                common_followers: std::collections::HashMap::new(),
+                common_preceders: std::collections::HashMap::new(),
                 chapter_affinity: std::collections::HashMap::new(),
                 dialogue_length_avg: 20.0,
             }
@@ -435,12 +1132,11 @@ mod tests {
         let current_json = serde_json::to_string(&all_patterns).unwrap();
         let rejected = "[\"speaker2\"]";
 
-        let result = update_from_feedback("test", "speaker1", rejected, &current_json);
-        let updated: std::collections::HashMap<String, SpeakerPattern> =
-            serde_json::from_str(&result).unwrap();
+        let result = update_from_feedback("test", "speaker1", "", rejected, "{}", "{}", &current_json);
+        let feedback: FeedbackResult = serde_json::from_str(&result).unwrap();
 
         // speaker2 should have been penalized (last_use decreased)
-        assert!(updated["speaker2"].last_used < all_patterns["speaker2"].last_used);
+        assert!(feedback.patterns["speaker2"].last_used < all_patterns["speaker2"].last_used);
     }
 
     #[test]
@@ -449,7 +1145,7 @@ mod tests {
             std::collections::HashMap::new();
         let all_json = serde_json::to_string(&all_patterns).unwrap();
 
-        let result = detect_speaker("test text", "chapter1", 5, &all_json);
+        let result = detect_speaker("test text", "chapter1", 5, "", "[]", &all_json);
         assert_eq!(result, "speaker1"); // Default fallback
     }
 
@@ -465,8 +1161,8 @@ mod tests {
                 xml_id: "speaker_recent".to_string(),
                 last_used: now - 1000, // Very recent
                 position_frequency: std::collections::HashMap::new(),
-                common_followers: Vec::new(),
-                common_preceders: Vec::new(),
+                common_followers: std::collections::HashMap::new(),
+                common_preceders: std::collections::HashMap::new(),
                 chapter_affinity: {
                     let mut map = std::collections::HashMap::new();
                     map.insert("chapter1".to_string(), 10.0);
@@ -483,17 +1179,381 @@ mod tests {
                 xml_id: "speaker_old".to_string(),
                 last_used: now - 10000000, // Very old
                 position_frequency: std::collections::HashMap::new(),
-                common_followers: Vec::new(),
-                common_preceders: Vec::new(),
+                common_followers: std::collections::HashMap::new(),
+                common_preceders: std::collections::HashMap::new(),
                 chapter_affinity: std::collections::HashMap::new(),
                 dialogue_length_avg: 5.0,
             }
         );
 
         let all_json = serde_json::to_string(&all_patterns).unwrap();
-        let result = detect_speaker("test text", "chapter1", 5, &all_json);
+        let result = detect_speaker("test text", "chapter1", 5, "", "[]", &all_json);
 
         // Recent speaker should be selected
         assert_eq!(result, "speaker_recent");
     }
+
+    #[test]
+    fn test_detect_speaker_transition_prefers_learned_follower() {
+        let mut all_patterns = std::collections::HashMap::new();
+
+        // speaker_a almost always hands off to speaker_b
+        let mut a_followers = std::collections::HashMap::new();
+        a_followers.insert("speaker_b".to_string(), 9);
+        a_followers.insert("speaker_c".to_string(), 1);
+        all_patterns.insert(
+            "speaker_a".to_string(),
+            SpeakerPattern {
+                xml_id: "speaker_a".to_string(),
+                last_used: 0,
+                position_frequency: std::collections::HashMap::new(),
+                common_followers: a_followers,
+                common_preceders: std::collections::HashMap::new(),
+                chapter_affinity: std::collections::HashMap::new(),
+                dialogue_length_avg: 5.0,
+            }
+        );
+
+        // speaker_b and speaker_c otherwise tie on every other factor
+        for id in ["speaker_b", "speaker_c"] {
+            all_patterns.insert(
+                id.to_string(),
+                SpeakerPattern {
+                    xml_id: id.to_string(),
+                    last_used: 0,
+                    position_frequency: std::collections::HashMap::new(),
+                    common_followers: std::collections::HashMap::new(),
+                    common_preceders: std::collections::HashMap::new(),
+                    chapter_affinity: std::collections::HashMap::new(),
+                    dialogue_length_avg: 0.0,
+                }
+            );
+        }
+
+        let all_json = serde_json::to_string(&all_patterns).unwrap();
+        let result = detect_speaker("test text", "chapter1", 5, "speaker_a", "[]", &all_json);
+
+        assert_eq!(result, "speaker_b");
+    }
+
+    #[test]
+    fn test_update_from_feedback_records_transition() {
+        let current = "{}";
+        let rejected = "[]";
+
+        // speaker_a -> speaker_b transition observed via feedback
+        let after_first =
+            update_from_feedback("hello", "speaker_a", "", rejected, "{}", "{}", current);
+        let after_first: FeedbackResult = serde_json::from_str(&after_first).unwrap();
+        let patterns_after_first = serde_json::to_string(&after_first.patterns).unwrap();
+
+        let result = update_from_feedback(
+            "world",
+            "speaker_b",
+            "speaker_a",
+            rejected,
+            "{}",
+            "{}",
+            &patterns_after_first,
+        );
+
+        let feedback: FeedbackResult = serde_json::from_str(&result).unwrap();
+        let all_patterns = feedback.patterns;
+
+        assert_eq!(
+            all_patterns["speaker_b"].common_preceders.get("speaker_a"),
+            Some(&1)
+        );
+        assert_eq!(
+            all_patterns["speaker_a"].common_followers.get("speaker_b"),
+            Some(&1)
+        );
+    }
+
+    #[test]
+    fn test_store_pattern_records_preceder() {
+        let result = store_pattern("speaker_b", "chapter1", 5, 10.0, "speaker_a", "{}");
+        let pattern: SpeakerPattern = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(pattern.common_preceders.get("speaker_a"), Some(&1));
+    }
+
+    #[test]
+    fn test_resolve_name_mention_exact() {
+        let speakers = vec![SpeakerName {
+            xml_id: "eliz".to_string(),
+            display_name: "Elizabeth".to_string(),
+        }];
+        let speakers_json = serde_json::to_string(&speakers).unwrap();
+
+        let result = resolve_name_mention("Elizabeth walked into the room", &speakers_json);
+        let name_match: NameMatch = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(name_match.name, "Elizabeth");
+        assert!(name_match.score > 0.9);
+    }
+
+    #[test]
+    fn test_resolve_name_mention_spelling_variant() {
+        let speakers = vec![SpeakerName {
+            xml_id: "eliz".to_string(),
+            display_name: "Elizabeth".to_string(),
+        }];
+        let speakers_json = serde_json::to_string(&speakers).unwrap();
+
+        // OCR/spelling variant: one substitution away from "Elizabeth"
+        let result = resolve_name_mention("Elisabeth smiled warmly", &speakers_json);
+        let name_match: NameMatch = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(name_match.name, "Elizabeth");
+        assert!(name_match.score > 0.5);
+    }
+
+    #[test]
+    fn test_resolve_name_mention_multiword_bigram() {
+        let speakers = vec![SpeakerName {
+            xml_id: "darcy".to_string(),
+            display_name: "Mr Darcy".to_string(),
+        }];
+        let speakers_json = serde_json::to_string(&speakers).unwrap();
+
+        let result = resolve_name_mention("She turned to Mr Darcy and bowed", &speakers_json);
+        let name_match: NameMatch = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(name_match.name, "Mr Darcy");
+        assert!(name_match.score > 0.9);
+    }
+
+    #[test]
+    fn test_resolve_name_mention_no_speakers() {
+        let result = resolve_name_mention("Anything at all", "[]");
+        let name_match: NameMatch = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(name_match.name, "");
+        assert_eq!(name_match.score, 0.0);
+    }
+
+    #[test]
+    fn test_resolve_name_mention_invalid_json() {
+        let result = resolve_name_mention("Anything at all", "not json");
+        let name_match: NameMatch = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(name_match.name, "");
+        assert_eq!(name_match.score, 0.0);
+    }
+
+    #[test]
+    fn test_cluster_passages_two_distinct_groups() {
+        let passages = vec![
+            "Where are you going?".to_string(),
+            "Where are we going?".to_string(),
+            "The house stood alone on the hill.".to_string(),
+            "The barn stood alone on the ridge.".to_string(),
+        ];
+        let passages_json = serde_json::to_string(&passages).unwrap();
+
+        let result = cluster_passages(&passages_json, 0.5, 2);
+        let clusters: Vec<PassageCluster> = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(clusters.len(), 2);
+        let mut all_indices: Vec<usize> = clusters
+            .iter()
+            .flat_map(|c| c.passage_indices.clone())
+            .collect();
+        all_indices.sort_unstable();
+        assert_eq!(all_indices, vec![0, 1, 2, 3]);
+
+        // The two questions should land in the same cluster, and the two
+        // declarative sentences in the other.
+        let question_cluster = clusters
+            .iter()
+            .find(|c| c.passage_indices.contains(&0))
+            .unwrap();
+        assert!(question_cluster.passage_indices.contains(&1));
+        assert!(!question_cluster.passage_indices.contains(&2));
+    }
+
+    #[test]
+    fn test_cluster_passages_threshold_keeps_everything_singleton() {
+        let passages = vec![
+            "Hello there friend".to_string(),
+            "Completely unrelated topic entirely".to_string(),
+        ];
+        let passages_json = serde_json::to_string(&passages).unwrap();
+
+        // A near-zero threshold should refuse to merge anything
+        let result = cluster_passages(&passages_json, 0.0, 0);
+        let clusters: Vec<PassageCluster> = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(clusters.len(), 2);
+    }
+
+    #[test]
+    fn test_cluster_passages_empty_input() {
+        let result = cluster_passages("[]", 0.5, 0);
+        let clusters: Vec<PassageCluster> = serde_json::from_str(&result).unwrap();
+
+        assert!(clusters.is_empty());
+    }
+
+    #[test]
+    fn test_cluster_passages_invalid_json() {
+        let result = cluster_passages("not json", 0.5, 0);
+        let clusters: Vec<PassageCluster> = serde_json::from_str(&result).unwrap();
+
+        assert!(clusters.is_empty());
+    }
+
+    #[test]
+    fn test_detect_speaker_force_rule_overrides_patterns() {
+        let mut all_patterns = std::collections::HashMap::new();
+        all_patterns.insert(
+            "speaker_recent".to_string(),
+            SpeakerPattern {
+                xml_id: "speaker_recent".to_string(),
+                last_used: get_current_time(),
+                position_frequency: std::collections::HashMap::new(),
+                common_followers: std::collections::HashMap::new(),
+                common_preceders: std::collections::HashMap::new(),
+                chapter_affinity: std::collections::HashMap::new(),
+                dialogue_length_avg: 0.0,
+            }
+        );
+        let all_json = serde_json::to_string(&all_patterns).unwrap();
+
+        let rules = vec![SpeakerRule {
+            xml_id: "narrator".to_string(),
+            predicate: Predicate::InChapter("chapter12".to_string()),
+            action: RuleAction::Force,
+        }];
+        let rules_json = serde_json::to_string(&rules).unwrap();
+
+        let result = detect_speaker("she said nothing", "chapter12", 3, "", &rules_json, &all_json);
+        assert_eq!(result, "narrator");
+    }
+
+    #[test]
+    fn test_detect_speaker_boost_rule_composes_with_model() {
+        let mut all_patterns = std::collections::HashMap::new();
+        for id in ["speaker_a", "speaker_b"] {
+            all_patterns.insert(
+                id.to_string(),
+                SpeakerPattern {
+                    xml_id: id.to_string(),
+                    last_used: 0,
+                    position_frequency: std::collections::HashMap::new(),
+                    common_followers: std::collections::HashMap::new(),
+                    common_preceders: std::collections::HashMap::new(),
+                    chapter_affinity: std::collections::HashMap::new(),
+                    dialogue_length_avg: 0.0,
+                }
+            );
+        }
+        let all_json = serde_json::to_string(&all_patterns).unwrap();
+
+        // Tied on every statistical factor, so the boost alone should decide
+        let rules = vec![SpeakerRule {
+            xml_id: "speaker_b".to_string(),
+            predicate: Predicate::AfterSpeaker("speaker_a".to_string()),
+            action: RuleAction::Boost(0.5),
+        }];
+        let rules_json = serde_json::to_string(&rules).unwrap();
+
+        let result = detect_speaker("hello", "chapter1", 0, "speaker_a", &rules_json, &all_json);
+        assert_eq!(result, "speaker_b");
+    }
+
+    #[test]
+    fn test_evaluate_predicate_combinators() {
+        let predicate = Predicate::AllOf(vec![
+            Predicate::InChapter("chapter12".to_string()),
+            Predicate::Not(Box::new(Predicate::NameMentioned("narrator".to_string()))),
+        ]);
+
+        assert!(evaluate_predicate(&predicate, "a stage cue follows", "chapter12", 0, ""));
+        assert!(!evaluate_predicate(
+            &predicate,
+            "the narrator speaks",
+            "chapter12",
+            0,
+            ""
+        ));
+        assert!(!evaluate_predicate(&predicate, "a stage cue follows", "chapter1", 0, ""));
+    }
+
+    #[test]
+    fn test_evaluate_predicate_matches_regex() {
+        let predicate = Predicate::MatchesRegex(r"^Dear\s+\w+,".to_string());
+
+        assert!(evaluate_predicate(&predicate, "Dear Elizabeth, I write in haste", "", 0, ""));
+        assert!(!evaluate_predicate(&predicate, "Sincerely yours", "", 0, ""));
+    }
+
+    #[test]
+    fn test_update_from_feedback_trains_confidence_model() {
+        let accepted_match = PatternMatch {
+            recent: true,
+            chapter_frequency: 1.0,
+            turn_taking: true,
+            name_mention: true,
+            dialogue_length_score: 1.0,
+        };
+        let mut pattern_matches = std::collections::HashMap::new();
+        pattern_matches.insert("speaker1".to_string(), accepted_match.clone());
+        let pattern_matches_json = serde_json::to_string(&pattern_matches).unwrap();
+
+        let result = update_from_feedback(
+            "test passage",
+            "speaker1",
+            "",
+            "[]",
+            &pattern_matches_json,
+            "{}",
+            "{}",
+        );
+        let feedback: FeedbackResult = serde_json::from_str(&result).unwrap();
+
+        // One gradient step toward target=1.0 should raise predicted
+        // confidence for the same accepted features above the untrained baseline
+        let baseline_model = ConfidenceModel::default();
+        let features = pattern_match_features(&accepted_match);
+        let baseline_confidence = confidence_from_features(&baseline_model, &features);
+        let trained_confidence = confidence_from_features(&feedback.model, &features);
+
+        assert!(trained_confidence > baseline_confidence);
+    }
+
+    #[test]
+    fn test_update_from_feedback_trains_against_rejected() {
+        let rejected_match = PatternMatch {
+            recent: true,
+            chapter_frequency: 1.0,
+            turn_taking: true,
+            name_mention: true,
+            dialogue_length_score: 1.0,
+        };
+        let mut pattern_matches = std::collections::HashMap::new();
+        pattern_matches.insert("speaker2".to_string(), rejected_match.clone());
+        let pattern_matches_json = serde_json::to_string(&pattern_matches).unwrap();
+
+        let result = update_from_feedback(
+            "test passage",
+            "speaker1",
+            "",
+            "[\"speaker2\"]",
+            &pattern_matches_json,
+            "{}",
+            "{}",
+        );
+        let feedback: FeedbackResult = serde_json::from_str(&result).unwrap();
+
+        // One gradient step toward target=0.0 should lower predicted
+        // confidence for the rejected speaker's features below the baseline
+        let baseline_model = ConfidenceModel::default();
+        let features = pattern_match_features(&rejected_match);
+        let baseline_confidence = confidence_from_features(&baseline_model, &features);
+        let trained_confidence = confidence_from_features(&feedback.model, &features);
+
+        assert!(trained_confidence < baseline_confidence);
+    }
 }